@@ -0,0 +1,152 @@
+use geoutils::Location;
+use regex::Regex;
+use std::error::Error;
+use std::fmt;
+
+// returned when a coordinate string doesn't match a format we understand,
+// or is out of range once parsed
+#[derive(Debug)]
+pub struct CoordinateParseError(String);
+
+impl fmt::Display for CoordinateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for CoordinateParseError {}
+
+/* accepts the formats people tend to copy out of a mapping app:
+ * - signed decimal degrees, e.g. "51.0978, -0.2434"
+ * - DMS with a hemisphere letter, e.g. "51° 05′ 52″ N 0° 14′ 36″ W"
+ * - degrees/decimal-minutes with a hemisphere letter, e.g. "51° 05.87′ N, 0° 14.60′ W"
+ */
+pub fn parse_coordinates(text: &str) -> Result<Location, CoordinateParseError> {
+    if let Some(location) = parse_decimal_pair(text) {
+        return validate(location);
+    }
+
+    if let Some(location) = parse_hemisphere_pair(text)? {
+        return validate(location);
+    }
+
+    Err(CoordinateParseError(format!(
+        "unrecognized coordinate format: {:?}",
+        text
+    )))
+}
+
+// "51.0978, -0.2434"
+fn parse_decimal_pair(text: &str) -> Option<Location> {
+    let re = Regex::new(r"^\s*(-?\d+(?:\.\d+)?)\s*,\s*(-?\d+(?:\.\d+)?)\s*$").unwrap();
+    let captures = re.captures(text)?;
+
+    let latitude: f64 = captures[1].parse().ok()?;
+    let longitude: f64 = captures[2].parse().ok()?;
+
+    Some(Location::new(latitude, longitude))
+}
+
+/* "51° 05′ 52″ N 0° 14′ 36″ W" (DMS) or "51° 05.87′ N, 0° 14.60′ W" (DDM) --
+ * minutes are a plain integer with seconds present (DMS), or a decimal
+ * value with no seconds present (DDM) */
+fn parse_hemisphere_pair(text: &str) -> Result<Option<Location>, CoordinateParseError> {
+    let re = Regex::new(
+        r#"(?i)(\d+)\s*°\s*(\d+(?:\.\d+)?)\s*['’′]\s*(?:(\d+(?:\.\d+)?)\s*["″”])?\s*([NSEW])"#,
+    )
+    .unwrap();
+
+    let mut latitude: Option<f64> = None;
+    let mut longitude: Option<f64> = None;
+
+    for captures in re.captures_iter(text) {
+        let degrees: f64 = captures[1].parse().unwrap();
+        let minutes: f64 = captures[2].parse().unwrap();
+        let seconds: f64 = captures
+            .get(3)
+            .map_or(0.0, |m| m.as_str().parse().unwrap());
+        let hemisphere = captures[4].to_ascii_uppercase();
+
+        let mut decimal_degrees = degrees + minutes / 60.0 + seconds / 3600.0;
+        if hemisphere == "S" || hemisphere == "W" {
+            decimal_degrees = -decimal_degrees;
+        }
+
+        match hemisphere.as_str() {
+            "N" | "S" => latitude = Some(decimal_degrees),
+            "E" | "W" => longitude = Some(decimal_degrees),
+            _ => unreachable!(),
+        }
+    }
+
+    match (latitude, longitude) {
+        (Some(latitude), Some(longitude)) => Ok(Some(Location::new(latitude, longitude))),
+        (None, None) => Ok(None),
+        _ => Err(CoordinateParseError(format!(
+            "found only one of latitude/longitude in {:?}",
+            text
+        ))),
+    }
+}
+
+fn validate(location: Location) -> Result<Location, CoordinateParseError> {
+    let latitude = location.latitude();
+    let longitude = location.longitude();
+
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(CoordinateParseError(format!(
+            "latitude {} is out of range (-90 to 90)",
+            latitude
+        )));
+    }
+
+    if !(-180.0..=180.0).contains(&longitude) {
+        return Err(CoordinateParseError(format!(
+            "longitude {} is out of range (-180 to 180)",
+            longitude
+        )));
+    }
+
+    Ok(location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signed_decimal_pair() {
+        let location = parse_coordinates("51.0978, -0.2434").unwrap();
+        assert!((location.latitude() - 51.0978).abs() < 1e-9);
+        assert!((location.longitude() - (-0.2434)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parses_dms_with_hemisphere() {
+        let location = parse_coordinates("51° 05′ 52″ N 0° 14′ 36″ W").unwrap();
+        assert!((location.latitude() - (51.0 + 5.0 / 60.0 + 52.0 / 3600.0)).abs() < 1e-6);
+        assert!((location.longitude() - -(0.0 + 14.0 / 60.0 + 36.0 / 3600.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parses_degrees_decimal_minutes_with_hemisphere() {
+        let location = parse_coordinates("51° 05.87′ N, 0° 14.60′ W").unwrap();
+        assert!((location.latitude() - (51.0 + 5.87 / 60.0)).abs() < 1e-6);
+        assert!((location.longitude() - -(0.0 + 14.60 / 60.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_out_of_range_latitude() {
+        assert!(parse_coordinates("91.0, 0.0").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_longitude() {
+        assert!(parse_coordinates("0.0, 181.0").is_err());
+    }
+
+    #[test]
+    fn rejects_unrecognized_text() {
+        assert!(parse_coordinates("not a coordinate").is_err());
+    }
+}
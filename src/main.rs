@@ -1,11 +1,71 @@
+mod coords;
+mod photos;
+mod search;
+mod spatial;
+
+use clap::{Parser, ValueEnum};
+use coords::parse_coordinates;
 use csv::Writer;
 use geoutils::Location;
-use kml::types::Geometry::Point;
-use kml::{Kml, KmlReader};
+use kml::types::Geometry::{LineString, Point};
+use kml::types::{Coord, KmlDocument, KmlVersion, Placemark, Point as KmlPoint};
+use kml::{Kml, KmlReader, KmlWriter};
 use regex::Regex;
 use serde::Serialize;
+use spatial::SpatialIndex;
+use std::collections::HashMap;
 use std::error::Error;
-use std::path::Path;
+use std::fs::File;
+use std::io::Write as IoWrite;
+use std::path::{Path, PathBuf};
+
+/// Command line options for the walk processor.
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Parse and filter Fancy Free Walks routes")]
+struct Cli {
+    /// Output format for the processed, sorted walk set
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    format: OutputFormat,
+
+    /// Home location to measure distance from: signed decimal degrees
+    /// ("51.0978, -0.2434"), DMS ("51° 05′ 52″ N 0° 14′ 36″ W"), or
+    /// degrees-decimal-minutes ("51° 05.87′ N, 0° 14.60′ W"). Defaults to
+    /// the area walks are usually sorted from.
+    #[arg(long)]
+    home: Option<String>,
+
+    /// Only keep walks whose start is within this many miles of home
+    #[arg(long)]
+    max_distance: Option<f64>,
+
+    /// Only keep walks no longer than this many miles
+    #[arg(long)]
+    max_length: Option<f64>,
+
+    /// Search walk names/descriptions for this text and print a ranked,
+    /// highlighted excerpt for each match instead of exporting
+    #[arg(long)]
+    search: Option<String>,
+
+    /// Match every geotagged JPEG in this directory to its nearest walk
+    /// instead of exporting
+    #[arg(long)]
+    photos: Option<PathBuf>,
+
+    /// When using --photos, drop photos whose nearest walk is farther than
+    /// this many miles away (unrelated to --max-distance, which filters
+    /// walks by distance from home)
+    #[arg(long)]
+    photo_max_distance: Option<f64>,
+}
+
+/// Supported export formats for the processed walk set.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Kml,
+    Gpx,
+}
 
 /* home (rather close to home, anyway!) */
 const HOME_LATITUDE: f64 = 51.097848;
@@ -75,7 +135,6 @@ fn parse_fancy_free_walks_map(element: Kml) -> Vec<Walk> {
             let mut length: f64 = 0.0;
             let mut latitude: f64 = 0.0;
             let mut longitude: f64 = 0.0;
-            let mut distance_miles: f64 = 0.0;
 
             // TODO pub_walk: check if includes the word pub (ignorecase)
             // TODO regex /www\.fancyfreewalks\.org.*$/gm to get the URL
@@ -113,27 +172,39 @@ fn parse_fancy_free_walks_map(element: Kml) -> Vec<Walk> {
                     Point(point) => {
                         latitude = point.coord.y;
                         longitude = point.coord.x;
+                    }
+                    LineString(line) => {
+                        /* prefer the real route geometry over the start marker */
+                        if line.coords.len() >= 2 {
+                            latitude = line.coords[0].y;
+                            longitude = line.coords[0].x;
+
+                            /* sum great-circle distance between consecutive vertices,
+                             * ignoring altitude and skipping degenerate segments */
+                            let mut route_meters = 0.0;
+                            for pair in line.coords.windows(2) {
+                                let start = Location::new(pair[0].y, pair[0].x);
+                                let end = Location::new(pair[1].y, pair[1].x);
+                                let segment_meters = start.distance_to(&end).unwrap().meters();
+                                if segment_meters > 0.0 {
+                                    route_meters += segment_meters;
+                                }
+                            }
 
-                        /* calculate distance from home to the start of the walk in miles */
-                        let home = Location::new(HOME_LATITUDE, HOME_LONGITUDE);
-                        let walk_start = Location::new(latitude, longitude);
-                        let distance = home.distance_to(&walk_start).unwrap();
-                        distance_miles = (distance.meters() * 0.006213712).round() / 10.0;
+                            /* only trust the route length if it amounted to something */
+                            if route_meters > 0.0 {
+                                length = spatial::miles_from_meters(route_meters);
+                            }
+                        }
                     }
                     _ => {}
                 },
                 _ => {}
             }
 
-            /* add walk into array */
-            let walk = Walk::new(
-                name,
-                description.unwrap(),
-                length,
-                latitude,
-                longitude,
-                distance_miles,
-            );
+            /* distance from home is filled in later, once the chosen home
+             * location is known */
+            let walk = Walk::new(name, description.unwrap(), length, latitude, longitude, 0.0);
             walks.push(walk);
         }
 
@@ -144,7 +215,113 @@ fn parse_fancy_free_walks_map(element: Kml) -> Vec<Walk> {
     walks
 }
 
+// build a short description for a walk placemark/waypoint, combining the
+// original text with the figures we computed for it
+fn export_description(walk: &Walk) -> String {
+    format!(
+        "{}\n\nLength: {:.1} miles\nDistance from home: {:.1} miles",
+        walk.description, walk.length, walk.distance
+    )
+}
+
+// serialize the processed walks as a KML Document of placemarks, so the
+// result can be dropped straight into a mapping app
+fn export_kml(walks: &[Walk], path: &Path) -> Result<(), Box<dyn Error>> {
+    let placemarks = walks
+        .iter()
+        .map(|walk| {
+            Kml::Placemark(Placemark {
+                name: Some(walk.name.clone()),
+                description: Some(export_description(walk)),
+                geometry: Some(Point(KmlPoint {
+                    coord: Coord {
+                        x: walk.longitude,
+                        y: walk.latitude,
+                        z: None,
+                    },
+                    ..Default::default()
+                })),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    let document = Kml::Document {
+        attrs: HashMap::new(),
+        elements: placemarks,
+    };
+
+    // a bare `Kml::Document` serializes without the `<?xml ...?>` header or
+    // `<kml>` root that a mapping app (or our own KmlReader) expects, so wrap
+    // it in a `KmlDocument` the way a real exported KMZ/KML file is structured
+    let kml_document = Kml::KmlDocument(KmlDocument {
+        version: KmlVersion::V23,
+        attrs: HashMap::new(),
+        elements: vec![document],
+    });
+
+    let mut kml_writer = KmlWriter::from_writer(File::create(path)?);
+    kml_writer.write(&kml_document)?;
+
+    Ok(())
+}
+
+// escape the handful of characters that are special in XML text/attribute
+// content
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// serialize the processed walks as GPX waypoints, for loading onto a GPS
+fn export_gpx(walks: &[Walk], path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(path)?;
+
+    writeln!(file, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        file,
+        r#"<gpx version="1.1" creator="fancy-free-walks" xmlns="http://www.topografix.com/GPX/1/1">"#
+    )?;
+    for walk in walks {
+        writeln!(
+            file,
+            r#"  <wpt lat="{}" lon="{}">"#,
+            walk.latitude, walk.longitude
+        )?;
+        writeln!(file, "    <name>{}</name>", escape_xml(&walk.name))?;
+        writeln!(
+            file,
+            "    <desc>{}</desc>",
+            escape_xml(&export_description(walk))
+        )?;
+        writeln!(file, "  </wpt>")?;
+    }
+    writeln!(file, "</gpx>")?;
+
+    Ok(())
+}
+
+// build a spatial index over each walk's start coordinate, keyed by its
+// position in `walks` so a lookup can be mapped straight back
+fn build_walk_index(walks: &[Walk]) -> SpatialIndex {
+    let points = walks
+        .iter()
+        .enumerate()
+        .map(|(id, walk)| (walk.latitude, walk.longitude, id))
+        .collect();
+    SpatialIndex::new(points)
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let home = match &cli.home {
+        Some(text) => parse_coordinates(text)?,
+        None => Location::new(HOME_LATITUDE, HOME_LONGITUDE),
+    };
+
     let kmz_path = Path::new("FancyFreeWalks Summary South East.kmz");
     let mut kmz_reader = KmlReader::<_, f64>::from_kmz_path(kmz_path).unwrap();
     let kmz_data = kmz_reader.read().unwrap();
@@ -152,18 +329,164 @@ fn main() -> Result<(), Box<dyn Error>> {
     // parse the walks from the kmz file
     let mut walks = parse_fancy_free_walks_map(kmz_data);
 
+    // build the index once over every parsed walk, before any home-radius
+    // filtering happens -- a photo's true nearest walk may well sit outside
+    // that radius, and `--max-distance`/`--photo-max-distance` are distinct
+    // thresholds that shouldn't shrink each other's candidate pool
+    let walk_index = build_walk_index(&walks);
+
+    // a photos directory matches geotagged JPEGs to their nearest walk
+    // instead of exporting
+    if let Some(dir) = &cli.photos {
+        let (matches, geotagged) =
+            photos::match_photos_to_walks(dir, &walk_index, cli.photo_max_distance)?;
+
+        println!("{} of the scanned photos were geotagged", geotagged);
+        for photo_match in &matches {
+            let walk = &walks[photo_match.walk_id];
+            println!(
+                "{}: {} ({:.1} miles away)",
+                photo_match.path.display(),
+                walk.name,
+                photo_match.distance_miles
+            );
+        }
+
+        return Ok(());
+    }
+
+    // fill in each walk's distance from the chosen home location via the
+    // spatial index; asking for every walk means every walk gets measured
+    for (id, miles) in walk_index.find_closest(&home, walks.len()) {
+        walks[id].distance = miles;
+    }
+
+    // keep only walks that fit the requested radius/length
+    walks.retain(|walk| {
+        cli.max_distance.map_or(true, |max| walk.distance <= max)
+            && cli.max_length.map_or(true, |max| walk.length <= max)
+    });
+
     // sort walks by distance
     walks.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
 
+    // a search query prints ranked excerpts instead of exporting
+    if let Some(query) = &cli.search {
+        let corpus: Vec<(usize, String)> = walks
+            .iter()
+            .enumerate()
+            .map(|(id, walk)| (id, format!("{} {}", walk.name, walk.description)))
+            .collect();
+
+        for hit in search::search(&corpus, query) {
+            println!("{}: {}", walks[hit.id].name, hit.excerpt);
+        }
+
+        return Ok(());
+    }
+
     // print walks
     println!("{:#?}", walks);
 
-    // export to csv
-    let mut csv = Writer::from_path("out.csv")?;
-    for walk in &walks {
-        csv.serialize(walk)?;
+    // export in the requested format
+    match cli.format {
+        OutputFormat::Csv => {
+            let mut csv = Writer::from_path("out.csv")?;
+            for walk in &walks {
+                csv.serialize(walk)?;
+            }
+            csv.flush()?;
+        }
+        OutputFormat::Kml => export_kml(&walks, Path::new("out.kml"))?,
+        OutputFormat::Gpx => export_gpx(&walks, Path::new("out.gpx"))?,
     }
-    csv.flush()?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kml::types::LineString as KmlLineString;
+
+    fn placemark_with_geometry(geometry: kml::types::Geometry) -> Kml {
+        Kml::Placemark(Placemark {
+            name: Some("Test Walk".to_string()),
+            description: Some("A nice 3 mile walk".to_string()),
+            geometry: Some(geometry),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn linestring_skips_degenerate_zero_length_segments() {
+        let kml = placemark_with_geometry(LineString(KmlLineString {
+            coords: vec![
+                Coord { x: -0.24, y: 51.10, z: None },
+                Coord { x: -0.24, y: 51.10, z: None }, // duplicate point, zero-length segment
+                Coord { x: -0.20, y: 51.12, z: None },
+            ],
+            ..Default::default()
+        }));
+
+        let walks = parse_fancy_free_walks_map(kml);
+
+        assert_eq!(walks.len(), 1);
+        assert_eq!(walks[0].latitude, 51.10);
+        assert_eq!(walks[0].longitude, -0.24);
+        // the degenerate segment contributes nothing; length comes from the
+        // one real segment, overriding the 3 mile figure from the description
+        assert!(walks[0].length > 0.0);
+        assert!((walks[0].length - 3.0).abs() > 1e-9);
+    }
+
+    #[test]
+    fn linestring_with_fewer_than_two_points_falls_back_to_description_length() {
+        let kml = placemark_with_geometry(LineString(KmlLineString {
+            coords: vec![Coord { x: -0.24, y: 51.10, z: None }],
+            ..Default::default()
+        }));
+
+        let walks = parse_fancy_free_walks_map(kml);
+
+        assert_eq!(walks.len(), 1);
+        // too few points to derive a position or route length from
+        assert_eq!(walks[0].latitude, 0.0);
+        assert_eq!(walks[0].longitude, 0.0);
+        assert_eq!(walks[0].length, 3.0);
+    }
+
+    #[test]
+    fn escape_xml_escapes_special_characters_without_double_escaping() {
+        assert_eq!(
+            escape_xml(r#"Fish & Chips <shop> "best""#),
+            "Fish &amp; Chips &lt;shop&gt; &quot;best&quot;"
+        );
+    }
+
+    #[test]
+    fn export_kml_round_trips_through_kml_reader() {
+        let walks = vec![Walk::new(
+            "Round Trip Walk".to_string(),
+            "A walk that comes back to itself".to_string(),
+            3.5,
+            51.10,
+            -0.24,
+            0.0,
+        )];
+
+        let path = std::env::temp_dir().join("fancy_free_walks_export_kml_test.kml");
+        export_kml(&walks, &path).unwrap();
+
+        let mut reader = KmlReader::<_, f64>::from_path(&path).unwrap();
+        let kml_data = reader.read().unwrap();
+        let parsed = parse_fancy_free_walks_map(kml_data);
+
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].name, "Round Trip Walk");
+        assert!((parsed[0].latitude - 51.10).abs() < 1e-9);
+        assert!((parsed[0].longitude - -0.24).abs() < 1e-9);
+    }
+}
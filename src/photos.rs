@@ -0,0 +1,121 @@
+use crate::spatial::SpatialIndex;
+use exif::{In, Reader, Tag, Value};
+use geoutils::Location;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+// a geotagged photo matched to the walk whose start is nearest to it
+pub struct PhotoMatch {
+    pub path: PathBuf,
+    pub walk_id: usize,
+    pub distance_miles: f64,
+}
+
+// convert an EXIF GPS rational triple (degrees, minutes, seconds) into
+// decimal degrees
+fn dms_to_decimal(value: &Value) -> Option<f64> {
+    match value {
+        Value::Rational(parts) if parts.len() == 3 => {
+            let degrees = parts[0].to_f64();
+            let minutes = parts[1].to_f64();
+            let seconds = parts[2].to_f64();
+            Some(degrees + minutes / 60.0 + seconds / 3600.0)
+        }
+        _ => None,
+    }
+}
+
+// read the GPS location out of a JPEG's EXIF tags, if it has one
+fn read_gps_location(path: &Path) -> Option<Location> {
+    let file = File::open(path).ok()?;
+    let mut reader = BufReader::new(file);
+    let exif = Reader::new().read_from_container(&mut reader).ok()?;
+
+    let latitude_field = exif.get_field(Tag::GPSLatitude, In::PRIMARY)?;
+    let latitude_ref = exif.get_field(Tag::GPSLatitudeRef, In::PRIMARY)?;
+    let longitude_field = exif.get_field(Tag::GPSLongitude, In::PRIMARY)?;
+    let longitude_ref = exif.get_field(Tag::GPSLongitudeRef, In::PRIMARY)?;
+
+    let mut latitude = dms_to_decimal(&latitude_field.value)?;
+    let mut longitude = dms_to_decimal(&longitude_field.value)?;
+
+    if latitude_ref.display_value().to_string().starts_with('S') {
+        latitude = -latitude;
+    }
+    if longitude_ref.display_value().to_string().starts_with('W') {
+        longitude = -longitude;
+    }
+
+    Some(Location::new(latitude, longitude))
+}
+
+/* scan dir for JPEGs, match each geotagged one to its nearest walk start
+ * using walk_index, and optionally drop photos whose nearest walk is
+ * further than max_distance miles away. returns the matches (nearest
+ * first isn't required here, one per photo) alongside how many of the
+ * scanned files actually carried GPS tags */
+pub fn match_photos_to_walks(
+    dir: &Path,
+    walk_index: &SpatialIndex,
+    max_distance: Option<f64>,
+) -> Result<(Vec<PhotoMatch>, usize), Box<dyn Error>> {
+    let mut matches = Vec::new();
+    let mut geotagged = 0;
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_jpeg = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .map_or(false, |ext| ext == "jpg" || ext == "jpeg");
+        if !is_jpeg {
+            continue;
+        }
+
+        let location = match read_gps_location(&path) {
+            Some(location) => location,
+            None => continue,
+        };
+        geotagged += 1;
+
+        let nearest = walk_index.find_closest(&location, 1);
+        if let Some((walk_id, distance_miles)) = nearest.into_iter().next() {
+            if max_distance.map_or(true, |max| distance_miles <= max) {
+                matches.push(PhotoMatch {
+                    path,
+                    walk_id,
+                    distance_miles,
+                });
+            }
+        }
+    }
+
+    Ok((matches, geotagged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::Rational;
+
+    #[test]
+    fn converts_dms_rational_triple_to_decimal_degrees() {
+        let value = Value::Rational(vec![
+            Rational { num: 51, denom: 1 },
+            Rational { num: 5, denom: 1 },
+            Rational { num: 52, denom: 1 },
+        ]);
+
+        let degrees = dms_to_decimal(&value).unwrap();
+
+        assert!((degrees - (51.0 + 5.0 / 60.0 + 52.0 / 3600.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_a_dms_triple() {
+        let value = Value::Rational(vec![Rational { num: 1, denom: 1 }]);
+        assert!(dms_to_decimal(&value).is_none());
+    }
+}
@@ -0,0 +1,181 @@
+use std::cmp::Reverse;
+
+// how many words wide the highlighted excerpt window is
+const WINDOW_WORDS: usize = 12;
+
+// a search hit: which item matched, and the best excerpt found in it
+pub struct SearchHit {
+    pub id: usize,
+    pub excerpt: String,
+}
+
+// split text into its words, preserving case (matching is done
+// case-insensitively separately) and discarding punctuation
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/* score of a single candidate window, used to rank windows against each
+ * other (and, in turn, items against each other). ordered so the
+ * *smallest* key is the best window: most distinct query words found (so
+ * wrapped in Reverse), then smallest gap between matches, then most
+ * matches appearing in query order (also Reverse) */
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct WindowKey(Reverse<usize>, usize, Reverse<usize>);
+
+// length of the longest subsequence of matches (by index into the query
+// word list), not necessarily contiguous, that appears in non-decreasing
+// query order, scanning left to right
+fn order_matches(query_indices: &[usize]) -> usize {
+    let mut best = vec![1usize; query_indices.len()];
+    for i in 0..query_indices.len() {
+        for j in 0..i {
+            if query_indices[j] <= query_indices[i] {
+                best[i] = best[i].max(best[j] + 1);
+            }
+        }
+    }
+    best.into_iter().max().unwrap_or(0)
+}
+
+// find the best-scoring window of WINDOW_WORDS words in text for the given
+// (already lowercased, deduplicated) query_words, and return its key plus
+// the rendered excerpt with matches wrapped in **markers**
+fn best_window(text: &str, query_words: &[String]) -> Option<(WindowKey, String)> {
+    let tokens = words(text);
+    if tokens.is_empty() {
+        return None;
+    }
+
+    let window_len = WINDOW_WORDS.min(tokens.len());
+    let mut best: Option<(WindowKey, usize)> = None;
+
+    for start in 0..=(tokens.len() - window_len) {
+        let end = start + window_len;
+
+        // (position within the window, index into query_words)
+        let matches: Vec<(usize, usize)> = tokens[start..end]
+            .iter()
+            .enumerate()
+            .filter_map(|(offset, word)| {
+                let lower = word.to_lowercase();
+                query_words
+                    .iter()
+                    .position(|query_word| *query_word == lower)
+                    .map(|query_index| (offset, query_index))
+            })
+            .collect();
+
+        if matches.is_empty() {
+            continue;
+        }
+
+        let distinct_words = matches
+            .iter()
+            .map(|(_, query_index)| *query_index)
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        let gap = matches.last().unwrap().0 - matches[0].0;
+        let order = order_matches(&matches.iter().map(|(_, qi)| *qi).collect::<Vec<_>>());
+
+        let key = WindowKey(Reverse(distinct_words), gap, Reverse(order));
+
+        if best.as_ref().map_or(true, |(best_key, _)| key < *best_key) {
+            best = Some((key, start));
+        }
+    }
+
+    let (key, start) = best?;
+    let end = start + window_len;
+    let excerpt = tokens[start..end]
+        .iter()
+        .map(|word| {
+            if query_words.contains(&word.to_lowercase()) {
+                format!("**{}**", word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Some((key, excerpt))
+}
+
+// rank corpus (id, text) entries by how well they match query, and return
+// the ones with at least one match, best excerpt first
+pub fn search(corpus: &[(usize, String)], query: &str) -> Vec<SearchHit> {
+    let query_words: Vec<String> = {
+        let mut seen = std::collections::HashSet::new();
+        words(query)
+            .into_iter()
+            .map(|word| word.to_lowercase())
+            .filter(|word| seen.insert(word.clone()))
+            .collect()
+    };
+
+    let mut ranked: Vec<(WindowKey, SearchHit)> = corpus
+        .iter()
+        .filter_map(|(id, text)| {
+            let (key, excerpt) = best_window(text, &query_words)?;
+            Some((key, SearchHit { id: *id, excerpt }))
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| a.0.cmp(&b.0));
+    ranked.into_iter().map(|(_, hit)| hit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_more_distinct_matches_above_fewer() {
+        let corpus = vec![
+            (1, "a walk past the river and the old mill".to_string()),
+            (2, "a walk through the woods near the river".to_string()),
+        ];
+
+        let hits = search(&corpus, "river mill");
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].id, 1);
+    }
+
+    #[test]
+    fn prefers_the_tighter_cluster_of_matches() {
+        let corpus = vec![
+            (
+                1,
+                "river one two three four five six seven eight mill".to_string(),
+            ),
+            (2, "river mill one two three four five six seven eight".to_string()),
+        ];
+
+        let hits = search(&corpus, "river mill");
+
+        assert_eq!(hits[0].id, 2);
+    }
+
+    #[test]
+    fn wraps_matched_words_in_markers() {
+        let corpus = vec![(1, "a walk past the river".to_string())];
+
+        let hits = search(&corpus, "river");
+
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].excerpt.contains("**river**"));
+    }
+
+    #[test]
+    fn items_with_no_matches_are_excluded() {
+        let corpus = vec![(1, "a walk past the river".to_string())];
+
+        let hits = search(&corpus, "mountain");
+
+        assert!(hits.is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use geoutils::Location;
+
+/* conservative lower bound on miles per degree of latitude (true figure is
+ * ~68.7-69.4 depending on latitude); staying below it just costs a few
+ * extra loop iterations below, never correctness */
+const MIN_MILES_PER_DEGREE_LATITUDE: f64 = 68.0;
+
+// same meters -> miles rounding used for the printed/exported figures
+pub fn miles_from_meters(meters: f64) -> f64 {
+    (meters * 0.006213712).round() / 10.0
+}
+
+pub fn distance_miles(a: &Location, b: &Location) -> f64 {
+    miles_from_meters(a.distance_to(b).unwrap().meters())
+}
+
+/* points sorted by latitude, so a "nearest to here" query doesn't have to
+ * measure the distance to every point up front. each point carries an
+ * opaque id the caller maps back to its own data (e.g. a Vec<Walk> index) */
+pub struct SpatialIndex {
+    points: Vec<(f64, f64, usize)>,
+}
+
+impl SpatialIndex {
+    // points given as (latitude, longitude, id)
+    pub fn new(mut points: Vec<(f64, f64, usize)>) -> SpatialIndex {
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        SpatialIndex { points }
+    }
+
+    // ids of the k points closest to origin, nearest first, with distance in miles
+    pub fn find_closest(&self, origin: &Location, k: usize) -> Vec<(usize, f64)> {
+        if self.points.is_empty() || k == 0 {
+            return Vec::new();
+        }
+
+        let origin_lat = origin.latitude();
+        let k = k.min(self.points.len());
+
+        // widen a latitude band around the origin until it's wide enough
+        // that every point outside it is *guaranteed* farther away than the
+        // kth-closest candidate found inside it so far -- just having "k
+        // candidates" in the band isn't enough, since a point just outside
+        // the band in latitude but close in longitude can still be nearer
+        // than an in-band point that is far away in longitude
+        let mut band_degrees: f64 = 1.0;
+        loop {
+            let mut distances: Vec<(usize, f64)> = self
+                .points
+                .iter()
+                .filter(|(lat, _, _)| (lat - origin_lat).abs() <= band_degrees)
+                .map(|(lat, lon, id)| (*id, distance_miles(origin, &Location::new(*lat, *lon))))
+                .collect();
+            distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+            let whole_world = band_degrees >= 180.0;
+            let band_bound_miles = band_degrees * MIN_MILES_PER_DEGREE_LATITUDE;
+            let kth_distance = distances.get(k - 1).map(|(_, miles)| *miles);
+            let band_is_wide_enough =
+                kth_distance.map_or(false, |kth| band_bound_miles >= kth);
+
+            if whole_world || band_is_wide_enough {
+                distances.truncate(k);
+                return distances;
+            }
+
+            band_degrees *= 2.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_true_nearest_point_outside_the_initial_band() {
+        // A is the true nearest point (76.0 miles) but its 1.1 degree
+        // latitude delta would exclude it from a naively-terminated 1
+        // degree starting band; B is within that band but much farther
+        // away in real distance.
+        let origin = Location::new(51.1, -0.24);
+        let point_a = (52.2, -0.24, 1);
+        let point_b = (51.05, 50.0, 2);
+
+        let index = SpatialIndex::new(vec![point_a, point_b]);
+        let closest = index.find_closest(&origin, 1);
+
+        assert_eq!(closest[0].0, 1);
+    }
+
+    #[test]
+    fn returns_k_nearest_points_in_order() {
+        let origin = Location::new(51.0, 0.0);
+        let index = SpatialIndex::new(vec![
+            (51.0, 0.1, 1),
+            (51.0, 1.0, 2),
+            (51.0, 0.5, 3),
+        ]);
+
+        let closest = index.find_closest(&origin, 2);
+
+        assert_eq!(closest.len(), 2);
+        assert_eq!(closest[0].0, 1);
+        assert_eq!(closest[1].0, 3);
+    }
+
+    #[test]
+    fn empty_index_returns_no_results() {
+        let index = SpatialIndex::new(Vec::new());
+        assert!(index.find_closest(&Location::new(0.0, 0.0), 1).is_empty());
+    }
+}